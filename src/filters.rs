@@ -0,0 +1,147 @@
+//! Scopes session queries to a directory, a date range, or the enclosing
+//! git repository, porting the contextual filtering (cwd scoping, git-root
+//! awareness, before/after windows) that shell-history databases offer.
+
+use crate::Session;
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime};
+use std::path::PathBuf;
+
+#[derive(Default)]
+pub struct OptFilters {
+    pub cwd: Option<String>,
+    pub before: Option<NaiveDateTime>,
+    pub after: Option<NaiveDateTime>,
+    pub git_root: Option<PathBuf>,
+}
+
+impl OptFilters {
+    pub fn is_empty(&self) -> bool {
+        self.cwd.is_none() && self.before.is_none() && self.after.is_none() && self.git_root.is_none()
+    }
+
+    pub fn matches(&self, session: &Session) -> bool {
+        if let Some(cwd) = &self.cwd {
+            if !under_dir(&session.pwd, cwd) {
+                return false;
+            }
+        }
+        if let Some(root) = &self.git_root {
+            if !under_dir(&session.pwd, &root.to_string_lossy()) {
+                return false;
+            }
+        }
+        if self.before.is_some() || self.after.is_some() {
+            let Some(created_at) = parse_created_at(&session.created_at) else {
+                return false;
+            };
+            if let Some(before) = self.before {
+                if created_at >= before {
+                    return false;
+                }
+            }
+            if let Some(after) = self.after {
+                if created_at <= after {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+fn under_dir(pwd: &str, dir: &str) -> bool {
+    let dir = dir.trim_end_matches('/');
+    pwd == dir || pwd.starts_with(&format!("{dir}/"))
+}
+
+/// Sessions normally get `created_at` via `save_session`'s `T`-separated
+/// timestamp, but rows imported without one fall back to SQLite's
+/// `datetime('now')` default, which uses a space separator instead.
+fn parse_created_at(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f"))
+        .ok()
+}
+
+/// Parses a `--before`/`--after` bound: an absolute date (`2024-01-05`) or a
+/// relative offset from now (`7d`, `2w`).
+pub fn parse_date_bound(s: &str) -> Result<NaiveDateTime, String> {
+    if let Some(datetime) = parse_relative(s) {
+        return Ok(datetime);
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+        .map_err(|_| format!("invalid date '{s}': expected YYYY-MM-DD, '7d', or '2w'"))
+}
+
+fn parse_relative(s: &str) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    let unit = s.chars().next_back()?;
+    // Slice off the unit by its UTF-8 length, not by one byte — the last
+    // char isn't guaranteed to be ASCII, and a byte-index slice panics on
+    // a multibyte one (e.g. "3\u{b2}").
+    let amount: i64 = s[..s.len() - unit.len_utf8()].parse().ok()?;
+    let duration = match unit {
+        'd' => Duration::days(amount),
+        'w' => Duration::weeks(amount),
+        _ => return None,
+    };
+    Some(Local::now().naive_local() - duration)
+}
+
+/// Walks up from the current directory looking for a `.git` folder to find
+/// the enclosing repository root.
+pub fn find_git_root() -> Option<PathBuf> {
+    let mut dir = std::env::current_dir().ok()?;
+    loop {
+        if dir.join(".git").exists() {
+            return Some(dir);
+        }
+        if !dir.pop() {
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_date_bound_accepts_absolute_date() {
+        let d = parse_date_bound("2024-01-05").unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2024, 1, 5).unwrap().and_hms_opt(0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn parse_date_bound_accepts_relative_days_and_weeks() {
+        let days = parse_date_bound("7d").unwrap();
+        let weeks = parse_date_bound("1w").unwrap();
+        assert!(days < Local::now().naive_local());
+        assert!(weeks < Local::now().naive_local());
+        // 1w and 7d are the same offset; the two results differ only by how
+        // far "now" moved between the calls, so just check both are in the
+        // ballpark instead of asserting exact equality.
+        assert!((weeks - days).num_seconds().abs() < 5);
+    }
+
+    #[test]
+    fn parse_date_bound_rejects_garbage() {
+        assert!(parse_date_bound("not-a-date").is_err());
+    }
+
+    #[test]
+    fn parse_relative_does_not_panic_on_multibyte_unit() {
+        // Regression test: the unit used to be sliced off by byte instead
+        // of by char, which panics on a non-ASCII last character.
+        assert_eq!(parse_relative("3\u{b2}"), None);
+        assert_eq!(parse_date_bound("3\u{b2}"), Err("invalid date '3\u{b2}': expected YYYY-MM-DD, '7d', or '2w'".to_string()));
+    }
+
+    #[test]
+    fn parse_created_at_accepts_both_separators() {
+        assert!(parse_created_at("2024-01-05T12:00:00.000000").is_some());
+        assert!(parse_created_at("2024-01-05 12:00:00").is_some());
+        assert!(parse_created_at("not-a-timestamp").is_none());
+    }
+}
@@ -0,0 +1,126 @@
+//! A small fzf-style fuzzy matcher: scores how well a query matches a
+//! candidate string as an ordered subsequence, rewarding consecutive runs
+//! and word-boundary starts so e.g. `cch find "clnup"` can still find
+//! "cleanup script".
+
+const CONSECUTIVE_BONUS: i32 = 16;
+const ADJACENT_BONUS: i32 = 8;
+const BOUNDARY_BONUS: i32 = 12;
+const LEADING_GAP_PENALTY: i32 = 1;
+
+fn is_boundary(c: char) -> bool {
+    matches!(c, '-' | '_' | '/' | ' ')
+}
+
+/// Returns `Some((score, matched_char_indices))` if `query`'s characters
+/// appear, in order, as a subsequence of `candidate` (case-insensitive).
+/// Returns `None` if there's no such match.
+pub fn score(query: &str, candidate: &str) -> Option<(i32, Vec<usize>)> {
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let candidate_chars: Vec<char> = candidate.chars().collect();
+    let candidate_lower: Vec<char> = candidate.to_lowercase().chars().collect();
+
+    let mut matches = Vec::with_capacity(query.len());
+    let mut score = 0i32;
+    let mut qi = 0;
+    let mut prev_match_idx: Option<usize> = None;
+
+    for (ci, &lower_c) in candidate_lower.iter().enumerate() {
+        if qi >= query.len() {
+            break;
+        }
+        if lower_c != query[qi] {
+            continue;
+        }
+
+        let mut char_score = CONSECUTIVE_BONUS;
+        if let Some(prev) = prev_match_idx {
+            if ci == prev + 1 {
+                char_score += ADJACENT_BONUS;
+            }
+        } else {
+            char_score -= (ci as i32) * LEADING_GAP_PENALTY;
+        }
+
+        let starts_at_boundary = ci == 0
+            || candidate_chars
+                .get(ci.wrapping_sub(1))
+                .is_some_and(|&p| is_boundary(p));
+        if starts_at_boundary {
+            char_score += BOUNDARY_BONUS;
+        }
+
+        score += char_score;
+        matches.push(ci);
+        prev_match_idx = Some(ci);
+        qi += 1;
+    }
+
+    if qi == query.len() {
+        Some((score, matches))
+    } else {
+        None
+    }
+}
+
+/// Wraps the matched characters of `s` (as produced by [`score`]) in ANSI
+/// bold so they stand out in terminal output.
+pub fn highlight(s: &str, matched_indices: &[usize]) -> String {
+    let mut out = String::with_capacity(s.len() + matched_indices.len() * 8);
+    for (i, c) in s.chars().enumerate() {
+        if matched_indices.contains(&i) {
+            out.push_str("\x1b[1m");
+            out.push(c);
+            out.push_str("\x1b[0m");
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_match_when_not_a_subsequence() {
+        assert_eq!(score("xyz", "cleanup script"), None);
+    }
+
+    #[test]
+    fn empty_query_matches_everything_with_zero_score() {
+        assert_eq!(score("", "anything"), Some((0, Vec::new())));
+    }
+
+    #[test]
+    fn matches_gappy_subsequence() {
+        assert!(score("clnup", "cleanup script").is_some());
+    }
+
+    #[test]
+    fn consecutive_run_scores_higher_than_scattered_match() {
+        // Neither candidate has a word-boundary character, so the only
+        // difference in play is how spread out the matched letters are.
+        let (consecutive, _) = score("log", "alogb").unwrap();
+        let (scattered, _) = score("log", "alxoxgx").unwrap();
+        assert!(consecutive > scattered);
+    }
+
+    #[test]
+    fn word_boundary_start_scores_higher_than_mid_word() {
+        // "fix" starts a word in "add-fix", but is buried mid-word in "prefix".
+        let (boundary, _) = score("fix", "add-fix").unwrap();
+        let (mid_word, _) = score("fix", "prefix").unwrap();
+        assert!(boundary > mid_word);
+    }
+
+    #[test]
+    fn is_case_insensitive() {
+        assert_eq!(score("FIX", "fix").unwrap().0, score("fix", "fix").unwrap().0);
+    }
+}
@@ -0,0 +1,206 @@
+//! Bulk-imports existing Claude Code session transcripts into the `cch` database,
+//! mirroring how shell-history tools seed themselves from a shell's existing history
+//! file instead of requiring every entry to be recorded by hand.
+
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+fn claude_projects_dir() -> PathBuf {
+    super::home_dir().join(".claude").join("projects")
+}
+
+/// Claude slugifies a project's working directory by replacing `/` with `-`
+/// to get a safe directory name. Reversing that blindly corrupts any path
+/// that itself contains a hyphen (`/Users/foo/my-project` slugifies to
+/// `-Users-foo-my-project`, which un-slugifies to `/Users/foo/my/project`),
+/// so this is only a fallback for when the transcript has no `cwd` of its
+/// own to read.
+fn unslugify_pwd(dir_name: &str) -> String {
+    dir_name.replace('-', "/")
+}
+
+/// Transcript events carry the session's actual working directory in a
+/// `cwd` field, which is the ground truth — prefer it over reconstructing
+/// `pwd` from the (lossy) slugified directory name.
+fn derive_pwd(path: &Path) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    for line in contents.lines() {
+        let event: Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if let Some(cwd) = event.get("cwd").and_then(Value::as_str) {
+            return Some(cwd.to_string());
+        }
+    }
+    None
+}
+
+fn ensure_metadata_table(db: &Connection) {
+    db.execute_batch(
+        "CREATE TABLE IF NOT EXISTS metadata (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+    )
+    .expect("Failed to create metadata table");
+}
+
+fn get_last_import(db: &Connection) -> Option<String> {
+    db.query_row(
+        "SELECT value FROM metadata WHERE key = 'last_import'",
+        [],
+        |row| row.get(0),
+    )
+    .ok()
+}
+
+fn set_last_import(db: &Connection, value: &str) {
+    db.execute(
+        "INSERT INTO metadata (key, value) VALUES ('last_import', ?1)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![value],
+    )
+    .expect("Failed to update last_import");
+}
+
+/// Pulls the first user prompt out of a transcript JSONL file, truncated for
+/// use as a session title.
+fn derive_title(path: &Path) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    for line in contents.lines() {
+        let event: Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if event.get("type").and_then(Value::as_str) != Some("user") {
+            continue;
+        }
+        let text = event
+            .get("message")
+            .and_then(|m| m.get("content"))
+            .and_then(extract_text);
+        if let Some(text) = text {
+            return Some(truncate(&text, 60));
+        }
+    }
+    None
+}
+
+fn extract_text(content: &Value) -> Option<String> {
+    if let Some(s) = content.as_str() {
+        return Some(s.to_string());
+    }
+    content.as_array()?.iter().find_map(|block| {
+        if block.get("type").and_then(Value::as_str) == Some("text") {
+            block.get("text").and_then(Value::as_str).map(str::to_string)
+        } else {
+            None
+        }
+    })
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    let s = s.trim();
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    let mut truncated: String = s.chars().take(max_chars).collect();
+    truncated.push_str("...");
+    truncated
+}
+
+pub fn import_sessions(incremental: bool) {
+    let db = super::get_db();
+    ensure_metadata_table(&db);
+
+    let last_import = if incremental {
+        get_last_import(&db)
+    } else {
+        None
+    };
+
+    let projects_dir = claude_projects_dir();
+    let Ok(project_entries) = fs::read_dir(&projects_dir) else {
+        println!("No Claude Code project transcripts found at {}.", projects_dir.display());
+        return;
+    };
+
+    let mut found = 0usize;
+    let mut imported = 0usize;
+
+    for project_entry in project_entries.flatten() {
+        if !project_entry.path().is_dir() {
+            continue;
+        }
+        let dir_name = project_entry.file_name().to_string_lossy().to_string();
+        let fallback_pwd = unslugify_pwd(&dir_name);
+
+        let Ok(transcript_entries) = fs::read_dir(project_entry.path()) else {
+            continue;
+        };
+
+        for transcript_entry in transcript_entries.flatten() {
+            let path = transcript_entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+                continue;
+            }
+
+            if let Some(cutoff) = &last_import {
+                if !modified_since(&path, cutoff) {
+                    continue;
+                }
+            }
+
+            let Some(session_id) = path.file_stem().map(|s| s.to_string_lossy().to_string())
+            else {
+                continue;
+            };
+
+            found += 1;
+            let pwd = derive_pwd(&path).unwrap_or_else(|| fallback_pwd.clone());
+            let title = derive_title(&path).unwrap_or_else(|| "(untitled session)".to_string());
+
+            let inserted = db
+                .execute(
+                    "INSERT OR IGNORE INTO sessions (id, title, pwd) VALUES (?1, ?2, ?3)",
+                    params![session_id, title, pwd],
+                )
+                .expect("Failed to import session");
+            imported += inserted;
+        }
+    }
+
+    // UTC, to match the UTC comparison `modified_since` does against file
+    // mtimes — mixing local and UTC here made `--incremental` off by the
+    // machine's UTC offset.
+    let now = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.6f").to_string();
+    set_last_import(&db, &now);
+
+    println!("Scanned {found} transcript(s), imported {imported} new session(s).");
+    if imported < found {
+        println!("({} already present)", found - imported);
+    }
+}
+
+fn modified_since(path: &Path, cutoff: &str) -> bool {
+    let Ok(cutoff) = chrono::NaiveDateTime::parse_from_str(cutoff, "%Y-%m-%dT%H:%M:%S%.f") else {
+        return true;
+    };
+    let Ok(metadata) = fs::metadata(path) else {
+        return true;
+    };
+    let Ok(modified) = metadata.modified() else {
+        return true;
+    };
+    let Ok(duration) = modified.duration_since(SystemTime::UNIX_EPOCH) else {
+        return true;
+    };
+    let Some(modified) = chrono::DateTime::from_timestamp(duration.as_secs() as i64, 0) else {
+        return true;
+    };
+    modified.naive_utc() > cutoff
+}
@@ -3,11 +3,19 @@ use clap::{Parser, Subcommand};
 use rusqlite::{Connection, params};
 use serde::Serialize;
 use std::env;
+use std::io::{self, Write};
 use std::os::unix::process::CommandExt; // Unix-only: exec() replaces the process
 use std::path::PathBuf;
 use std::process::Command;
 use tiny_http::{Header, Method, Response, Server};
 
+mod filters;
+mod fuzzy;
+mod import;
+mod sync;
+
+use filters::OptFilters;
+
 const DASHBOARD_HTML: &str = include_str!("../dashboard.html");
 
 fn db_path() -> PathBuf {
@@ -34,6 +42,9 @@ fn get_db() -> Connection {
         )",
     )
     .expect("Failed to create table");
+    // Keeps `version`/tombstones current even if `cch sync` has never run
+    // here, so a later sync has accurate history to push.
+    sync::ensure_schema(&conn);
     conn
 }
 
@@ -51,24 +62,48 @@ fn save_session(session_id: &str, title: &str) {
         .to_string_lossy()
         .to_string();
     let now = Local::now().format("%Y-%m-%dT%H:%M:%S%.6f").to_string();
+    let updated_at = sync::now_ts();
     let db = get_db();
+
+    // If this id was already deleted (and thus has a tombstone), resurrect
+    // it one version past the tombstone rather than resetting to 1 — a
+    // fresh version 1 can never beat the server's own record of the
+    // tombstone's version, so the resurrection would never propagate.
+    let next_version: i64 = db
+        .query_row(
+            "SELECT version FROM tombstones WHERE id = ?1",
+            params![session_id],
+            |row| row.get(0),
+        )
+        .map(|tombstone_version: i64| tombstone_version + 1)
+        .unwrap_or(1);
+
     db.execute(
-        "INSERT OR REPLACE INTO sessions (id, title, pwd, created_at) VALUES (?1, ?2, ?3, ?4)",
-        params![session_id, title, pwd, now],
+        "INSERT INTO sessions (id, title, pwd, created_at, version, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(id) DO UPDATE SET
+            title = excluded.title, pwd = excluded.pwd, created_at = excluded.created_at,
+            version = sessions.version + 1, updated_at = excluded.updated_at",
+        params![session_id, title, pwd, now, next_version, updated_at],
     )
     .expect("Failed to save session");
+    // A re-saved id is no longer deleted as far as sync is concerned.
+    db.execute("DELETE FROM tombstones WHERE id = ?1", params![session_id])
+        .ok();
     println!("Saved: {title}");
     println!("  ID:  {session_id}");
     println!("  Dir: {pwd}");
 }
 
-fn list_sessions(limit: usize) {
+fn list_sessions(limit: usize, filters: &OptFilters) {
     let db = get_db();
-    let mut stmt = db
-        .prepare("SELECT id, title, pwd, created_at FROM sessions ORDER BY created_at DESC LIMIT ?1")
-        .unwrap();
-    let rows: Vec<Session> = stmt
-        .query_map(params![limit], |row| {
+    let rows: Vec<Session> = if filters.is_empty() {
+        let mut stmt = db
+            .prepare(
+                "SELECT id, title, pwd, created_at FROM sessions ORDER BY created_at DESC LIMIT ?1",
+            )
+            .unwrap();
+        stmt.query_map(params![limit], |row| {
             Ok(Session {
                 id: row.get(0)?,
                 title: row.get(1)?,
@@ -78,7 +113,14 @@ fn list_sessions(limit: usize) {
         })
         .unwrap()
         .map(|r| r.expect("failed to read session row"))
-        .collect();
+        .collect()
+    } else {
+        get_all_sessions(&db)
+            .into_iter()
+            .filter(|s| filters.matches(s))
+            .take(limit)
+            .collect()
+    };
 
     if rows.is_empty() {
         println!("No saved sessions.");
@@ -100,7 +142,60 @@ fn list_sessions(limit: usize) {
     }
 }
 
-fn search_sessions(query: &str) {
+fn search_sessions(query: &str, exact: bool, filters: &OptFilters) {
+    if exact {
+        search_sessions_exact(query, filters);
+        return;
+    }
+
+    let db = get_db();
+    let rows = get_all_sessions(&db);
+
+    let mut scored: Vec<(i32, Vec<usize>, &Session)> = rows
+        .iter()
+        .filter(|s| filters.matches(s))
+        .filter_map(|s| {
+            let title_match = fuzzy::score(query, &s.title);
+            let id_match = fuzzy::score(query, &s.id);
+            let title_score = title_match.as_ref().map(|(score, _)| *score);
+            let id_score = id_match.as_ref().map(|(score, _)| *score);
+            match (title_score, id_score) {
+                (Some(ts), Some(ids)) if ids > ts => Some((ids, Vec::new(), s)),
+                (Some(ts), _) => Some((ts, title_match.unwrap().1, s)),
+                (None, Some(ids)) => Some((ids, Vec::new(), s)),
+                (None, None) => None,
+            }
+        })
+        .collect();
+
+    if scored.is_empty() {
+        println!("No sessions matching '{query}'.");
+        return;
+    }
+
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.2.created_at.cmp(&a.2.created_at)));
+
+    for (i, (_, matched_indices, s)) in scored.iter().enumerate() {
+        let ts = &s.created_at[..std::cmp::min(16, s.created_at.len())].replace('T', " ");
+        let title = if matched_indices.is_empty() {
+            s.title.clone()
+        } else {
+            fuzzy::highlight(&s.title, matched_indices)
+        };
+        println!("[{}] {}", i + 1, title);
+        println!("    ID:  {}", s.id);
+        println!(
+            "    Cmd: claude --resume {} --dangerously-skip-permissions",
+            s.id
+        );
+        println!("    Dir: {}  ({ts})", s.pwd);
+        if i < scored.len() - 1 {
+            println!();
+        }
+    }
+}
+
+fn search_sessions_exact(query: &str, filters: &OptFilters) {
     let db = get_db();
     let pattern = format!("%{query}%");
     let mut stmt = db
@@ -117,6 +212,7 @@ fn search_sessions(query: &str) {
         })
         .unwrap()
         .map(|r| r.expect("failed to read session row"))
+        .filter(|s| filters.matches(s))
         .collect();
 
     if rows.is_empty() {
@@ -221,6 +317,27 @@ fn do_resume(session_id: &str, pwd: &str, title: &str) {
     eprintln!("Failed to exec claude: {err}");
 }
 
+/// Deletes a session and leaves a tombstone behind (bumping its version) so
+/// `cch sync` can propagate the delete instead of the row just disappearing.
+fn mark_deleted(db: &Connection, id: &str) {
+    let version: i64 = db
+        .query_row(
+            "SELECT version FROM sessions WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .unwrap_or(1);
+    db.execute("DELETE FROM sessions WHERE id = ?1", params![id])
+        .ok();
+    let now = sync::now_ts();
+    db.execute(
+        "INSERT INTO tombstones (id, version, deleted_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(id) DO UPDATE SET version = excluded.version, deleted_at = excluded.deleted_at",
+        params![id, version + 1, now],
+    )
+    .ok();
+}
+
 fn delete_session(identifier: &str) {
     let db = get_db();
 
@@ -228,8 +345,7 @@ fn delete_session(identifier: &str) {
         let rows = get_all_sessions(&db);
         if idx >= 1 && idx <= rows.len() {
             let s = &rows[idx - 1];
-            db.execute("DELETE FROM sessions WHERE id = ?1", params![s.id])
-                .unwrap();
+            mark_deleted(&db, &s.id);
             let short = &s.id[..std::cmp::min(8, s.id.len())];
             println!("Deleted: {} ({short}...)", s.title);
             return;
@@ -238,24 +354,76 @@ fn delete_session(identifier: &str) {
         return;
     }
 
-    let deleted = db
-        .execute("DELETE FROM sessions WHERE id = ?1", params![identifier])
-        .expect("failed to delete session");
-    if deleted > 0 {
-        println!("Deleted {deleted} session(s).");
+    if db
+        .query_row(
+            "SELECT 1 FROM sessions WHERE id = ?1",
+            params![identifier],
+            |row| row.get::<_, i64>(0),
+        )
+        .is_ok()
+    {
+        mark_deleted(&db, identifier);
+        println!("Deleted 1 session(s).");
         return;
     }
+
     let pattern = format!("%{identifier}%");
-    let deleted = db
-        .execute("DELETE FROM sessions WHERE id LIKE ?1", params![pattern])
-        .expect("failed to delete session");
-    if deleted > 0 {
-        println!("Deleted {deleted} session(s).");
+    let ids: Vec<String> = {
+        let mut stmt = db.prepare("SELECT id FROM sessions WHERE id LIKE ?1").unwrap();
+        stmt.query_map(params![pattern], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.expect("failed to read session id"))
+            .collect()
+    };
+    if !ids.is_empty() {
+        for id in &ids {
+            mark_deleted(&db, id);
+        }
+        println!("Deleted {} session(s).", ids.len());
     } else {
         println!("No session found for '{identifier}'.");
     }
 }
 
+/// Prompts `Do you want to continue? [y/N]` unless `yes` is set, returning
+/// whether the caller should proceed.
+fn confirm(prompt: &str, yes: bool) -> bool {
+    if yes {
+        return true;
+    }
+    print!("{prompt} Do you want to continue? [y/N] ");
+    io::stdout().flush().ok();
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).is_err() {
+        return false;
+    }
+    matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn bulk_delete(filters: &OptFilters, description: &str, yes: bool) {
+    let db = get_db();
+    let matching: Vec<Session> = get_all_sessions(&db)
+        .into_iter()
+        .filter(|s| filters.matches(s))
+        .collect();
+
+    if matching.is_empty() {
+        println!("No sessions match {description}.");
+        std::process::exit(1);
+    }
+
+    let prompt = format!("This will delete {} session(s) {description}.", matching.len());
+    if !confirm(&prompt, yes) {
+        println!("Aborted.");
+        return;
+    }
+
+    for s in &matching {
+        mark_deleted(&db, &s.id);
+    }
+    println!("Deleted {} session(s).", matching.len());
+}
+
 fn start_web(port: u16) {
     let addr = format!("127.0.0.1:{port}");
     let server = Server::http(&addr).expect("Failed to start server");
@@ -282,8 +450,7 @@ fn start_web(port: u16) {
             (&Method::Delete, url) if url.starts_with("/api/sessions/") => {
                 let session_id = &url["/api/sessions/".len()..];
                 let db = get_db();
-                db.execute("DELETE FROM sessions WHERE id = ?1", params![session_id])
-                    .ok();
+                mark_deleted(&db, session_id);
                 let response = Response::from_string(r#"{"ok":true}"#)
                     .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
                 request.respond(response).ok();
@@ -303,6 +470,45 @@ struct Cli {
     command: Option<Commands>,
 }
 
+#[derive(clap::Args)]
+struct FilterArgs {
+    /// Only show sessions started under this directory
+    #[arg(long = "dir")]
+    dir: Option<String>,
+    /// Only show sessions created before this date (YYYY-MM-DD, or relative like 7d, 2w)
+    #[arg(long)]
+    before: Option<String>,
+    /// Only show sessions created after this date (YYYY-MM-DD, or relative like 7d, 2w)
+    #[arg(long)]
+    after: Option<String>,
+    /// Only show sessions started within the current git repository
+    #[arg(long)]
+    here: bool,
+}
+
+fn resolve_filters(args: &FilterArgs) -> OptFilters {
+    let parse_bound = |s: &Option<String>| -> Option<chrono::NaiveDateTime> {
+        s.as_ref().map(|s| match filters::parse_date_bound(s) {
+            Ok(d) => d,
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        })
+    };
+
+    OptFilters {
+        cwd: args.dir.clone(),
+        before: parse_bound(&args.before),
+        after: parse_bound(&args.after),
+        git_root: if args.here {
+            filters::find_git_root()
+        } else {
+            None
+        },
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Save a session
@@ -316,11 +522,18 @@ enum Commands {
     Ls {
         #[arg(short, default_value = "20")]
         n: usize,
+        #[command(flatten)]
+        filters: FilterArgs,
     },
     /// Search sessions by title or ID
     #[command(alias = "f")]
     Find {
         query: String,
+        /// Fall back to plain substring matching instead of fuzzy ranking
+        #[arg(long)]
+        exact: bool,
+        #[command(flatten)]
+        filters: FilterArgs,
     },
     /// Resume a session
     #[command(alias = "r")]
@@ -330,7 +543,26 @@ enum Commands {
     /// Delete a saved session
     #[command(alias = "del")]
     Rm {
-        identifier: String,
+        identifier: Option<String>,
+        /// Delete every saved session
+        #[arg(long)]
+        all: bool,
+        /// Delete sessions older than this (e.g. 30d, 2w)
+        #[arg(long = "older-than")]
+        older_than: Option<String>,
+        /// Delete all sessions started under this directory
+        #[arg(long)]
+        dir: Option<String>,
+        /// Skip the confirmation prompt for multi-row deletes
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Import session transcripts from ~/.claude/projects
+    #[command(alias = "i")]
+    Import {
+        /// Only import transcripts modified since the last import
+        #[arg(long)]
+        incremental: bool,
     },
     /// Open the web dashboard
     #[command(alias = "w")]
@@ -338,6 +570,23 @@ enum Commands {
         #[arg(short, long, default_value = "5111")]
         port: u16,
     },
+    /// Run a sync server that other machines can push/pull sessions from
+    Server {
+        #[arg(short, long, default_value = "5112")]
+        port: u16,
+    },
+    /// Sync sessions with a remote `cch server`
+    Sync {
+        /// Base URL of the sync server, e.g. http://example.com:5112
+        #[arg(long)]
+        server: String,
+        /// Account to sync under; rows are partitioned by this on the server
+        #[arg(long)]
+        user: String,
+        /// Passphrase used to derive the client-side encryption key
+        #[arg(long, env = "CCH_SYNC_PASSPHRASE")]
+        passphrase: String,
+    },
 }
 
 fn main() {
@@ -345,8 +594,8 @@ fn main() {
     let raw_args: Vec<String> = env::args().collect();
     if raw_args.len() >= 3 {
         let known = [
-            "save", "s", "ls", "list", "find", "f", "resume", "r", "rm", "del", "web", "w",
-            "-h", "--help", "help",
+            "save", "s", "ls", "list", "find", "f", "resume", "r", "rm", "del", "import", "i",
+            "web", "w", "server", "sync", "-h", "--help", "help",
         ];
         if !known.contains(&raw_args[1].as_str()) {
             save_session(&raw_args[1], &raw_args[2]);
@@ -358,11 +607,56 @@ fn main() {
 
     match cli.command {
         Some(Commands::Save { session_id, title }) => save_session(&session_id, &title),
-        Some(Commands::Ls { n }) => list_sessions(n),
-        Some(Commands::Find { query }) => search_sessions(&query),
+        Some(Commands::Ls { n, filters }) => list_sessions(n, &resolve_filters(&filters)),
+        Some(Commands::Find {
+            query,
+            exact,
+            filters,
+        }) => search_sessions(&query, exact, &resolve_filters(&filters)),
         Some(Commands::Resume { identifier }) => resume_session(&identifier),
-        Some(Commands::Rm { identifier }) => delete_session(&identifier),
+        Some(Commands::Rm {
+            identifier,
+            all,
+            older_than,
+            dir,
+            yes,
+        }) => {
+            if all {
+                bulk_delete(&OptFilters::default(), "(all sessions)", yes);
+            } else if let Some(older_than) = older_than {
+                let before = match filters::parse_date_bound(&older_than) {
+                    Ok(d) => d,
+                    Err(err) => {
+                        eprintln!("{err}");
+                        std::process::exit(1);
+                    }
+                };
+                let filters = OptFilters {
+                    before: Some(before),
+                    ..Default::default()
+                };
+                bulk_delete(&filters, &format!("older than '{older_than}'"), yes);
+            } else if let Some(dir) = dir {
+                let filters = OptFilters {
+                    cwd: Some(dir.clone()),
+                    ..Default::default()
+                };
+                bulk_delete(&filters, &format!("under '{dir}'"), yes);
+            } else if let Some(identifier) = identifier {
+                delete_session(&identifier);
+            } else {
+                eprintln!("Specify a session to delete, or one of --all, --older-than, --dir.");
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Import { incremental }) => import::import_sessions(incremental),
         Some(Commands::Web { port }) => start_web(port),
+        Some(Commands::Server { port }) => sync::run_server(port),
+        Some(Commands::Sync {
+            server,
+            user,
+            passphrase,
+        }) => sync::sync_sessions(&server, &user, &passphrase),
         None => {
             Cli::parse_from(["cch", "--help"]);
         }
@@ -0,0 +1,402 @@
+//! Multi-machine session sync. `cch server` runs a small REST endpoint that
+//! stores per-user session rows; `cch sync` pushes local rows to it and pulls
+//! down anything new, the way shell-history tools split into an async
+//! client/server pair with incremental, end-to-end-encrypted uploads.
+
+use aes_gcm::aead::{Aead, AeadCore, OsRng};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tiny_http::{Header, Method, Response, Server};
+
+/// UTC, fixed-width, lexicographically sortable — used for every timestamp
+/// that a string comparison (the `since`/`updated_at` high-water mark) has
+/// to order correctly across machines in different time zones.
+const TIMESTAMP_FMT: &str = "%Y-%m-%dT%H:%M:%S%.6f";
+
+/// Also used by `main.rs` to stamp `sessions.updated_at` on every local
+/// mutation, so the push side of a sync can filter to rows changed since
+/// the last round-trip instead of re-uploading the whole table.
+pub fn now_ts() -> String {
+    Utc::now().format(TIMESTAMP_FMT).to_string()
+}
+
+/// Ensures the columns and tables this subsystem needs exist. Called from
+/// `get_db()` so every mutation path (save, rm, import) keeps `version` and
+/// tombstones up to date even if `cch sync` has never run on this machine.
+pub fn ensure_schema(db: &Connection) {
+    // SQLite has no "ADD COLUMN IF NOT EXISTS"; ignore the error if it's
+    // already there from a previous run.
+    db.execute("ALTER TABLE sessions ADD COLUMN host_id TEXT", [])
+        .ok();
+    db.execute(
+        "ALTER TABLE sessions ADD COLUMN version INTEGER NOT NULL DEFAULT 1",
+        [],
+    )
+    .ok();
+    // Nullable: existing rows predate this column, and a NULL is treated
+    // as "never pushed" by the sync_sessions query below, so they still
+    // go out on the next push instead of being silently skipped.
+    db.execute("ALTER TABLE sessions ADD COLUMN updated_at TEXT", [])
+        .ok();
+    db.execute_batch(
+        "CREATE TABLE IF NOT EXISTS metadata (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS tombstones (
+            id TEXT PRIMARY KEY,
+            version INTEGER NOT NULL,
+            deleted_at TEXT NOT NULL
+        )",
+    )
+    .expect("Failed to create sync metadata tables");
+}
+
+fn get_host_id(db: &Connection) -> String {
+    if let Ok(id) = db.query_row(
+        "SELECT value FROM metadata WHERE key = 'host_id'",
+        [],
+        |row| row.get::<_, String>(0),
+    ) {
+        return id;
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    db.execute(
+        "INSERT INTO metadata (key, value) VALUES ('host_id', ?1)",
+        params![id],
+    )
+    .expect("Failed to store host_id");
+    id
+}
+
+fn get_sync_time(db: &Connection) -> String {
+    db.query_row(
+        "SELECT value FROM metadata WHERE key = 'sync_time'",
+        [],
+        |row| row.get(0),
+    )
+    .unwrap_or_else(|_| "1970-01-01T00:00:00.000000".to_string())
+}
+
+fn set_sync_time(db: &Connection, value: &str) {
+    db.execute(
+        "INSERT INTO metadata (key, value) VALUES ('sync_time', ?1)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![value],
+    )
+    .expect("Failed to update sync_time");
+}
+
+/// Derives a symmetric key from a user passphrase via Argon2id, salted with
+/// the sync account name. Rows are AES-256-GCM encrypted with this key
+/// before they ever leave the client, so the server only ever sees
+/// ciphertext. The salt has to be something every machine syncing under the
+/// same account already has on hand (there's no shared-secret round-trip
+/// before the first sync), so it's derived from `user` rather than stored
+/// — that's enough to stop the same passphrase hashing identically across
+/// unrelated accounts, while Argon2's work factor makes offline brute-force
+/// of the passphrase itself expensive.
+fn derive_key(user: &str, passphrase: &str) -> Aes256Gcm {
+    let mut hasher = Sha256::new();
+    hasher.update(user.as_bytes());
+    let salt = hasher.finalize();
+
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .expect("argon2 key derivation failed");
+    Aes256Gcm::new_from_slice(&key).expect("key is always 32 bytes")
+}
+
+fn encrypt_field(cipher: &Aes256Gcm, plaintext: &str) -> String {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .expect("encryption failed");
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+    BASE64.encode(payload)
+}
+
+fn decrypt_field(cipher: &Aes256Gcm, encoded: &str) -> String {
+    let Ok(payload) = BASE64.decode(encoded) else {
+        return String::new();
+    };
+    if payload.len() < 12 {
+        return String::new();
+    }
+    let (nonce_bytes, ciphertext) = payload.split_at(12);
+    let nonce = Nonce::from_slice(nonce_bytes);
+    match cipher.decrypt(nonce, ciphertext) {
+        Ok(plaintext) => String::from_utf8_lossy(&plaintext).to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SyncRow {
+    id: String,
+    title: String,
+    pwd: String,
+    created_at: String,
+    host_id: String,
+    version: i64,
+    deleted: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SyncRequest {
+    user: String,
+    since: String,
+    rows: Vec<SyncRow>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SyncResponse {
+    rows: Vec<SyncRow>,
+    server_time: String,
+}
+
+fn ensure_server_table(db: &Connection) {
+    db.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sync_rows (
+            user TEXT NOT NULL,
+            id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            pwd TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            host_id TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            deleted INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (user, id)
+        )",
+    )
+    .expect("Failed to create sync_rows table");
+}
+
+pub fn run_server(port: u16) {
+    let db = super::get_db();
+    ensure_server_table(&db);
+
+    // Not loopback-only: this is meant to be reachable from other machines
+    // per `cch sync --server http://host:port`. Put it behind a firewall or
+    // reverse proxy with TLS/auth if it's exposed beyond a trusted network —
+    // row payloads are encrypted, but the REST API itself has none.
+    let addr = format!("0.0.0.0:{port}");
+    let server = Server::http(&addr).expect("Failed to start sync server");
+    println!("cch sync server listening on {addr}");
+
+    for mut request in server.incoming_requests() {
+        if request.method() != &Method::Post || request.url() != "/sync" {
+            let response = Response::from_string("Not Found").with_status_code(404);
+            request.respond(response).ok();
+            continue;
+        }
+
+        let mut body = String::new();
+        if request.as_reader().read_to_string(&mut body).is_err() {
+            let response = Response::from_string("Bad Request").with_status_code(400);
+            request.respond(response).ok();
+            continue;
+        }
+
+        let Ok(req): Result<SyncRequest, _> = serde_json::from_str(&body) else {
+            let response = Response::from_string("Bad Request").with_status_code(400);
+            request.respond(response).ok();
+            continue;
+        };
+
+        let received_at = now_ts();
+        for row in &req.rows {
+            db.execute(
+                "INSERT INTO sync_rows (user, id, title, pwd, created_at, host_id, version, deleted, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                 ON CONFLICT(user, id) DO UPDATE SET
+                    title = excluded.title, pwd = excluded.pwd, created_at = excluded.created_at,
+                    host_id = excluded.host_id, version = excluded.version, deleted = excluded.deleted,
+                    updated_at = excluded.updated_at
+                 WHERE excluded.version > sync_rows.version",
+                params![
+                    req.user, row.id, row.title, row.pwd, row.created_at, row.host_id,
+                    row.version, row.deleted as i64, received_at
+                ],
+            )
+            .ok();
+        }
+
+        let mut stmt = db
+            .prepare(
+                "SELECT id, title, pwd, created_at, host_id, version, deleted
+                 FROM sync_rows WHERE user = ?1 AND updated_at > ?2",
+            )
+            .unwrap();
+        let rows: Vec<SyncRow> = stmt
+            .query_map(params![req.user, req.since], |r| {
+                Ok(SyncRow {
+                    id: r.get(0)?,
+                    title: r.get(1)?,
+                    pwd: r.get(2)?,
+                    created_at: r.get(3)?,
+                    host_id: r.get(4)?,
+                    version: r.get(5)?,
+                    deleted: r.get::<_, i64>(6)? != 0,
+                })
+            })
+            .unwrap()
+            .map(|r| r.expect("failed to read sync row"))
+            .collect();
+
+        let response_body = serde_json::to_string(&SyncResponse {
+            rows,
+            server_time: received_at,
+        })
+        .unwrap();
+        let response = Response::from_string(response_body)
+            .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+        request.respond(response).ok();
+    }
+}
+
+pub fn sync_sessions(server: &str, user: &str, passphrase: &str) {
+    let db = super::get_db();
+    let key = derive_key(user, passphrase);
+    let host_id = get_host_id(&db);
+    let since = get_sync_time(&db);
+
+    // Incremental diff upload: only send rows touched since the last
+    // successful sync (by `updated_at`/`deleted_at`), not the whole table.
+    // A NULL `updated_at` (a row that predates that column, or one written
+    // by `import` or pulled from the server) is treated as never-pushed and
+    // sent anyway.
+    let mut stmt = db
+        .prepare(
+            "SELECT id, title, pwd, created_at, host_id, version FROM sessions
+             WHERE updated_at IS NULL OR updated_at > ?1",
+        )
+        .unwrap();
+    let mut local_rows: Vec<SyncRow> = stmt
+        .query_map(params![since], |r| {
+            Ok(SyncRow {
+                id: r.get(0)?,
+                title: r.get(1)?,
+                pwd: r.get(2)?,
+                created_at: r.get(3)?,
+                host_id: r.get::<_, Option<String>>(4)?.unwrap_or_else(|| host_id.clone()),
+                version: r.get(5)?,
+                deleted: false,
+            })
+        })
+        .unwrap()
+        .map(|r| r.expect("failed to read session row"))
+        .collect();
+
+    // Deletes leave a tombstone (see `mark_deleted` in main.rs) rather than
+    // just disappearing, so the version bump and delete still reach peers.
+    let mut tomb_stmt = db
+        .prepare("SELECT id, version, deleted_at FROM tombstones WHERE deleted_at > ?1")
+        .unwrap();
+    let tombstones: Vec<SyncRow> = tomb_stmt
+        .query_map(params![since], |r| {
+            Ok(SyncRow {
+                id: r.get(0)?,
+                title: String::new(),
+                pwd: String::new(),
+                created_at: r.get::<_, String>(2)?,
+                host_id: host_id.clone(),
+                version: r.get(1)?,
+                deleted: true,
+            })
+        })
+        .unwrap()
+        .map(|r| r.expect("failed to read tombstone row"))
+        .collect();
+    local_rows.extend(tombstones);
+
+    let encrypted_rows: Vec<SyncRow> = local_rows
+        .iter()
+        .map(|row| SyncRow {
+            id: row.id.clone(),
+            title: encrypt_field(&key, &row.title),
+            pwd: encrypt_field(&key, &row.pwd),
+            created_at: row.created_at.clone(),
+            host_id: row.host_id.clone(),
+            version: row.version,
+            deleted: row.deleted,
+        })
+        .collect();
+
+    let req = SyncRequest {
+        user: user.to_string(),
+        since: since.clone(),
+        rows: encrypted_rows,
+    };
+    let body = serde_json::to_string(&req).unwrap();
+
+    let url = format!("{}/sync", server.trim_end_matches('/'));
+    let response = match ureq::post(&url).send_string(&body) {
+        Ok(resp) => resp,
+        Err(err) => {
+            eprintln!("Sync failed: {err}");
+            return;
+        }
+    };
+    let resp: SyncResponse = match response.into_json() {
+        Ok(r) => r,
+        Err(err) => {
+            eprintln!("Sync failed: could not parse server response ({err})");
+            return;
+        }
+    };
+
+    let mut applied = 0;
+    for row in &resp.rows {
+        if row.host_id == host_id {
+            continue;
+        }
+        if row.deleted {
+            db.execute("DELETE FROM sessions WHERE id = ?1", params![row.id])
+                .ok();
+            let changed = db
+                .execute(
+                    "INSERT INTO tombstones (id, version, deleted_at) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(id) DO UPDATE SET version = excluded.version, deleted_at = excluded.deleted_at
+                     WHERE excluded.version > tombstones.version",
+                    params![row.id, row.version, row.created_at],
+                )
+                .unwrap_or(0);
+            applied += changed;
+            continue;
+        }
+        let title = decrypt_field(&key, &row.title);
+        let pwd = decrypt_field(&key, &row.pwd);
+        let changed = db
+            .execute(
+                "INSERT INTO sessions (id, title, pwd, created_at, host_id, version)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(id) DO UPDATE SET
+                    title = excluded.title, pwd = excluded.pwd, created_at = excluded.created_at,
+                    host_id = excluded.host_id, version = excluded.version
+                 WHERE excluded.version > sessions.version",
+                params![row.id, title, pwd, row.created_at, row.host_id, row.version],
+            )
+            .unwrap_or(0);
+        if changed > 0 {
+            db.execute("DELETE FROM tombstones WHERE id = ?1", params![row.id])
+                .ok();
+        }
+        applied += changed;
+    }
+
+    set_sync_time(&db, &resp.server_time);
+    println!(
+        "Synced: pushed {} row(s), pulled {applied} new row(s).",
+        local_rows.len()
+    );
+}